@@ -1,6 +1,10 @@
 // this file have been cloned from https://github.com/wizardsardine/liana/pull/597
 
 use iced::Color;
+use palette::{FromColor, Hsl, Srgb};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
 pub const BLACK: Color = iced::Color::BLACK;
 pub const TRANSPARENT: Color = iced::Color::TRANSPARENT;
 pub const LIGHT_BLACK: Color = Color::from_rgb(
@@ -28,7 +32,6 @@ pub const GREY_2: Color = Color::from_rgb(
     0xCC as f32 / 255.0,
     0xCC as f32 / 255.0,
 );
-#[allow(dead_code)]
 pub const GREY_1: Color = Color::from_rgb(
     0xE6 as f32 / 255.0,
     0xE6 as f32 / 255.0,
@@ -40,3 +43,350 @@ pub const GREEN: Color = Color::from_rgb(
     0xFF as f32 / 255.0,
     0x66 as f32 / 255.0,
 );
+
+/// Generate a color at each of `lightness_stops`, holding `base`'s hue and saturation fixed. Lets
+/// a theme derive a full tint/shade family (greys, or an accent like [`GREEN`]) from a single base
+/// color instead of requiring every stop to be typed out by hand. Stops needn't be evenly spaced;
+/// pass the exact lightness values a ladder needs to reproduce.
+pub fn shade_ramp(base: Color, lightness_stops: &[f32]) -> Vec<Color> {
+    let hsl = Hsl::from_color(Srgb::new(base.r, base.g, base.b));
+    lightness_stops
+        .iter()
+        .map(|&lightness| {
+            let shade = Srgb::from_color(Hsl::new(hsl.hue, hsl.saturation, lightness));
+            Color::from_rgba(shade.red, shade.green, shade.blue, base.a)
+        })
+        .collect()
+}
+
+/// The grey ramp backing [`Theme::default_theme`], darkest to lightest, reproduced from
+/// [`LIGHT_BLACK`]/[`GREY_6`]/[`GREY_7`]/[`GREY_3`]/[`GREY_2`]/[`GREY_1`]'s own lightness so the
+/// built-in theme matches those constants exactly.
+fn default_greys() -> Vec<Color> {
+    let lightness_stops = [LIGHT_BLACK, GREY_6, GREY_7, GREY_3, GREY_2, GREY_1].map(|c| c.r);
+    shade_ramp(Color::from_rgb(0.5, 0.5, 0.5), &lightness_stops)
+}
+
+/// A `Color` deserializable from a CSS color string (hex forms, `rgb()`/`rgba()`, `hsl()`/`hsla()`,
+/// or a named color), so a `Theme` can be loaded from a plain-text config file instead of
+/// requiring raw f32 triples.
+#[derive(Debug, Clone, Copy)]
+pub struct CssColor(pub Color);
+
+impl<'de> Deserialize<'de> for CssColor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        parse_color(&s).map(CssColor).map_err(serde::de::Error::custom)
+    }
+}
+
+/// An invalid CSS color string passed to [`parse_color`].
+#[derive(Debug, Clone)]
+pub struct ParseError(String);
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid CSS color: '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a CSS Color Module color string: hex (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`),
+/// `rgb()`/`rgba()`, `hsl()`/`hsla()`, or a named color.
+fn parse_color(s: &str) -> Result<Color, ParseError> {
+    let s = s.trim();
+    if let Some(hex) = s.strip_prefix('#') {
+        return parse_hex_color(hex).ok_or_else(|| ParseError(s.to_string()));
+    }
+    if let Some(args) = s.strip_prefix("rgb(").or_else(|| s.strip_prefix("rgba(")) {
+        let args = args
+            .strip_suffix(')')
+            .ok_or_else(|| ParseError(s.to_string()))?;
+        return parse_rgb_function(args).ok_or_else(|| ParseError(s.to_string()));
+    }
+    if let Some(args) = s.strip_prefix("hsl(").or_else(|| s.strip_prefix("hsla(")) {
+        let args = args
+            .strip_suffix(')')
+            .ok_or_else(|| ParseError(s.to_string()))?;
+        return parse_hsl_function(args).ok_or_else(|| ParseError(s.to_string()));
+    }
+    named_color(s).ok_or_else(|| ParseError(s.to_string()))
+}
+
+fn parse_hex_color(s: &str) -> Option<Color> {
+    let channel = |i: usize, len: usize| -> Option<u8> {
+        let digits = &s.get(i..i + len)?;
+        let v = u8::from_str_radix(digits, 16).ok()?;
+        Some(if len == 1 { v * 0x11 } else { v })
+    };
+    match s.len() {
+        3 => Some(Color::from_rgb8(channel(0, 1)?, channel(1, 1)?, channel(2, 1)?)),
+        4 => Some(Color::from_rgba8(
+            channel(0, 1)?,
+            channel(1, 1)?,
+            channel(2, 1)?,
+            channel(3, 1)? as f32 / 255.0,
+        )),
+        6 => Some(Color::from_rgb8(channel(0, 2)?, channel(2, 2)?, channel(4, 2)?)),
+        8 => Some(Color::from_rgba8(
+            channel(0, 2)?,
+            channel(2, 2)?,
+            channel(4, 2)?,
+            channel(6, 2)? as f32 / 255.0,
+        )),
+        _ => None,
+    }
+}
+
+/// Split `rgb()`/`hsl()` function arguments into owned tokens on commas, slashes, or whitespace,
+/// per the CSS syntax that allows both `rgb(255, 0, 0)` and `rgb(255 0 0 / 50%)`.
+fn split_args(args: &str) -> Vec<String> {
+    args.replace([',', '/'], " ")
+        .split_whitespace()
+        .map(String::from)
+        .collect()
+}
+
+fn parse_channel(s: &str) -> Option<u8> {
+    if let Some(pct) = s.strip_suffix('%') {
+        let pct: f32 = pct.parse().ok()?;
+        Some((pct.clamp(0.0, 100.0) / 100.0 * 255.0).round() as u8)
+    } else {
+        let v: f32 = s.parse().ok()?;
+        Some(v.clamp(0.0, 255.0).round() as u8)
+    }
+}
+
+fn parse_rgb_function(args: &str) -> Option<Color> {
+    let parts = split_args(args);
+    let (r, g, b) = (parse_channel(parts.first()?)?, parse_channel(parts.get(1)?)?, parse_channel(parts.get(2)?)?);
+    match parts.get(3) {
+        None => Some(Color::from_rgb8(r, g, b)),
+        Some(a) => Some(Color::from_rgba8(r, g, b, parse_alpha(a)?)),
+    }
+}
+
+fn parse_alpha(s: &str) -> Option<f32> {
+    if let Some(pct) = s.strip_suffix('%') {
+        Some(pct.parse::<f32>().ok()?.clamp(0.0, 100.0) / 100.0)
+    } else {
+        Some(s.parse::<f32>().ok()?.clamp(0.0, 1.0))
+    }
+}
+
+/// Parse an angle in degrees, accepting `deg`, `grad`, `rad` and `turn` units (bare numbers are
+/// degrees, per the CSS `<angle>` syntax used by `hsl()`).
+fn parse_angle_degrees(s: &str) -> Option<f32> {
+    if let Some(v) = s.strip_suffix("deg") {
+        v.parse().ok()
+    } else if let Some(v) = s.strip_suffix("grad") {
+        v.parse::<f32>().ok().map(|g| g * 0.9)
+    } else if let Some(v) = s.strip_suffix("rad") {
+        v.parse::<f32>().ok().map(|r| r.to_degrees())
+    } else if let Some(v) = s.strip_suffix("turn") {
+        v.parse::<f32>().ok().map(|t| t * 360.0)
+    } else {
+        s.parse().ok()
+    }
+}
+
+/// Convert HSL to RGB following the standard CSS algorithm:
+/// `C = (1 - |2L - 1|) * S`, `X = C * (1 - |(H/60 mod 2) - 1|)`, `m = L - C/2`, then pick the
+/// `(R', G', B')` sextant by `H/60` before adding `m` back to each channel.
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r1, g1, b1) = match h as u32 / 60 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    (r1 + m, g1 + m, b1 + m)
+}
+
+fn parse_hsl_function(args: &str) -> Option<Color> {
+    let parts = split_args(args);
+    let h = parse_angle_degrees(parts.first()?)?;
+    let s = parse_channel_percent(parts.get(1)?)?;
+    let l = parse_channel_percent(parts.get(2)?)?;
+    let (r, g, b) = hsl_to_rgb(h, s, l);
+    match parts.get(3) {
+        None => Some(Color::from_rgb(r, g, b)),
+        Some(a) => Some(Color::from_rgba(r, g, b, parse_alpha(a)?)),
+    }
+}
+
+fn parse_channel_percent(s: &str) -> Option<f32> {
+    let pct = s.strip_suffix('%')?;
+    Some(pct.parse::<f32>().ok()?.clamp(0.0, 100.0) / 100.0)
+}
+
+/// A handful of CSS named colors covering this theme's palette; extend as new names are needed.
+fn named_color(s: &str) -> Option<Color> {
+    Some(match s.to_ascii_lowercase().as_str() {
+        "black" => Color::BLACK,
+        "white" => Color::WHITE,
+        "transparent" => Color::TRANSPARENT,
+        "red" => Color::from_rgb8(0xFF, 0x00, 0x00),
+        "lime" => Color::from_rgb8(0x00, 0xFF, 0x00),
+        "green" => Color::from_rgb8(0x00, 0x80, 0x00),
+        "blue" => Color::from_rgb8(0x00, 0x00, 0xFF),
+        "grey" | "gray" => Color::from_rgb8(0x80, 0x80, 0x80),
+        "yellow" => Color::from_rgb8(0xFF, 0xFF, 0x00),
+        "orange" => Color::from_rgb8(0xFF, 0xA5, 0x00),
+        _ => return None,
+    })
+}
+
+/// A named set of colors the installer's GUI draws from. Loaded from a TOML file so users can
+/// swap in their own palette without recompiling, mirroring how the current hardcoded constants
+/// are exposed as the built-in "default" theme below.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Theme {
+    pub black: CssColor,
+    pub transparent: CssColor,
+    pub light_black: CssColor,
+    pub grey_7: CssColor,
+    pub grey_6: CssColor,
+    pub grey_3: CssColor,
+    pub grey_2: CssColor,
+    pub grey_1: CssColor,
+    pub white: CssColor,
+    pub green: CssColor,
+}
+
+impl Theme {
+    /// The theme matching this module's original hardcoded constants.
+    pub fn default_theme() -> Self {
+        let greys = default_greys();
+        Self {
+            black: CssColor(BLACK),
+            transparent: CssColor(TRANSPARENT),
+            light_black: CssColor(greys[0]),
+            grey_6: CssColor(greys[1]),
+            grey_7: CssColor(greys[2]),
+            grey_3: CssColor(greys[3]),
+            grey_2: CssColor(greys[4]),
+            grey_1: CssColor(greys[5]),
+            white: CssColor(WHITE),
+            green: CssColor(GREEN),
+        }
+    }
+
+    /// Load a theme from a TOML file.
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// The default config file location: `ledger_installer/theme.toml` under the platform's config
+    /// directory (e.g. `~/.config/ledger_installer/theme.toml` on Linux).
+    pub fn config_path() -> Option<PathBuf> {
+        Some(dirs::config_dir()?.join("ledger_installer").join("theme.toml"))
+    }
+
+    /// Load the user's theme file if present, falling back to the built-in default otherwise.
+    pub fn load() -> Self {
+        Self::config_path()
+            .filter(|p| p.exists())
+            .and_then(|p| Self::from_file(&p).ok())
+            .unwrap_or_else(Self::default_theme)
+    }
+
+    /// Check this theme's foreground/background pairings against the WCAG AA contrast
+    /// thresholds, returning one warning per pairing that falls short. `load` does not call this;
+    /// it applies a theme file without checking its contrast.
+    pub fn validate(&self) -> Vec<ContrastWarning> {
+        let pairs: &[(&str, Color, &str, Color, TextSize)] = &[
+            ("white", self.white.0, "black", self.black.0, TextSize::Normal),
+            ("grey_1", self.grey_1.0, "light_black", self.light_black.0, TextSize::Normal),
+            ("grey_2", self.grey_2.0, "light_black", self.light_black.0, TextSize::Large),
+            ("green", self.green.0, "black", self.black.0, TextSize::Large),
+        ];
+        pairs
+            .iter()
+            .filter_map(|&(foreground, fg, background, bg, size)| {
+                let ratio = contrast_ratio(fg, bg);
+                let required = size.aa_threshold();
+                (ratio < required).then_some(ContrastWarning {
+                    foreground,
+                    background,
+                    size,
+                    ratio,
+                    required,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Whether a foreground/background pairing is used for normal-sized or large text, since WCAG AA
+/// sets a lower contrast bar (3.0:1) for large text than for normal text (4.5:1).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextSize {
+    Normal,
+    Large,
+}
+
+impl TextSize {
+    fn aa_threshold(self) -> f32 {
+        match self {
+            TextSize::Normal => 4.5,
+            TextSize::Large => 3.0,
+        }
+    }
+}
+
+/// A theme foreground/background pairing that falls below the WCAG AA contrast threshold for
+/// its text size, returned by [`Theme::validate`].
+#[derive(Debug, Clone)]
+pub struct ContrastWarning {
+    pub foreground: &'static str,
+    pub background: &'static str,
+    pub size: TextSize,
+    pub ratio: f32,
+    pub required: f32,
+}
+
+impl std::fmt::Display for ContrastWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} on {} has contrast {:.2}:1, below the {:.1}:1 AA threshold for {:?} text",
+            self.foreground, self.background, self.ratio, self.required, self.size
+        )
+    }
+}
+
+impl std::error::Error for ContrastWarning {}
+
+/// The WCAG relative luminance of a color: linearize each sRGB channel, then weight by
+/// `0.2126*R + 0.7152*G + 0.0722*B`.
+fn relative_luminance(c: Color) -> f32 {
+    let linearize = |c: f32| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linearize(c.r) + 0.7152 * linearize(c.g) + 0.0722 * linearize(c.b)
+}
+
+/// The WCAG contrast ratio between two colors: `(Lmax + 0.05) / (Lmin + 0.05)`, where `Lmax`/`Lmin`
+/// are the lighter/darker of the two relative luminances. Ranges from 1.0 (no contrast) to 21.0
+/// (black on white).
+pub fn contrast_ratio(fg: Color, bg: Color) -> f32 {
+    let (l1, l2) = (relative_luminance(fg), relative_luminance(bg));
+    let (lighter, darker) = if l1 >= l2 { (l1, l2) } else { (l2, l1) };
+    (lighter + 0.05) / (darker + 0.05)
+}
@@ -50,6 +50,24 @@ const OPEN_APP_COMMAND_TEMPLATE: APDUCommand<&[u8]> = APDUCommand {
     data: &[],
 };
 
+// https://github.com/LedgerHQ/ledger-live/blob/99879eb5bada1ecaea7a02d8886e16b44657af6d/libs/ledger-live-common/src/hw/getAppAndVersion.ts#L13
+const GET_APP_AND_VERSION_COMMAND: APDUCommand<&[u8]> = APDUCommand {
+    cla: 0xb0,
+    ins: 0x01,
+    p1: 0x00,
+    p2: 0x00,
+    data: &[],
+};
+
+// https://github.com/LedgerHQ/ledger-live/blob/99879eb5bada1ecaea7a02d8886e16b44657af6d/libs/ledger-live-common/src/hw/quitApp.ts#L3
+const QUIT_APP_COMMAND: APDUCommand<&[u8]> = APDUCommand {
+    cla: 0xb0,
+    ins: 0xa7,
+    p1: 0x00,
+    p2: 0x00,
+    data: &[],
+};
+
 /// The Ledger Live API requires request to set their claimed version of Ledger Live. This was
 /// chosen arbitrarily as a working value.
 pub const LIVE_COMMON_VERSION: &str = "34.0.0";
@@ -293,6 +311,16 @@ fn deser_apdu_command(hex_str: &str) -> Result<APDUCommand<Vec<u8>>, Box<dyn err
     })
 }
 
+/// Progress of an install/update/genuine-check exchange with Ledger's remote HSM, reported after
+/// each APDU command processed over the websocket.
+#[derive(Debug, Clone, Copy)]
+pub struct InstallProgress {
+    /// Number of APDU commands processed so far, across the whole websocket exchange.
+    pub current: usize,
+    /// Number of commands in the current "bulk" message, when known.
+    pub total: Option<usize>,
+}
+
 /// Some actions, such as installing apps or upgrading the firmware, are done in Ledger Live by
 /// opening a socket so a remote server communicates directly with the Ledger. It appears to be
 /// talking to an HSM up there which would manage sensitive actions.
@@ -300,8 +328,19 @@ fn deser_apdu_command(hex_str: &str) -> Result<APDUCommand<Vec<u8>>, Box<dyn err
 pub fn query_via_websocket(
     ledger_api: &TransportNativeHID,
     url: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    query_via_websocket_with_progress(ledger_api, url, &mut |_| {})
+}
+
+/// Same as `query_via_websocket`, but calls `progress` after every APDU command exchanged with
+/// the device so a caller can render a progress bar instead of an indeterminate spinner.
+pub fn query_via_websocket_with_progress(
+    ledger_api: &TransportNativeHID,
+    url: &str,
+    progress: &mut dyn FnMut(InstallProgress),
 ) -> Result<(), Box<dyn error::Error>> {
     let (mut socket, _) = tungstenite::connect(url)?;
+    let mut current = 0;
 
     // https://github.com/LedgerHQ/ledger-live/blob/99879eb5bada1ecaea7a02d8886e16b44657af6d/libs/ledger-live-common/src/socket/index.ts#L95
     loop {
@@ -336,6 +375,11 @@ pub fn query_via_websocket(
                         "error"
                     };
                     let resp_data = hex::encode(resp.data());
+                    current += 1;
+                    progress(InstallProgress {
+                        current,
+                        total: None,
+                    });
 
                     let ws_resp = serde_json::json!({
                         "nonce": msg.nonce,
@@ -353,12 +397,15 @@ pub fn query_via_websocket(
                         Some(HsmMessageData::CommandList(l)) => l,
                         _ => return Err("Expecting a list of commands in bulk mode.".into()),
                     };
+                    let total = Some(commands.len());
                     for cmd_hex in commands {
                         if cmd_hex.is_empty() {
                             continue;
                         }
                         let command = deser_apdu_command(&cmd_hex)?;
                         let _ = ledger_api.exchange(&command)?;
+                        current += 1;
+                        progress(InstallProgress { current, total });
                     }
 
                     let ws_resp = serde_json::json!({
@@ -570,6 +617,23 @@ pub fn bitcoin_apps_by_hashes(
     Ok(resp_apps.json::<Vec<_>>()?.into_iter().collect())
 }
 
+/// Get the full app catalog available for this device's target id and firmware version. This
+/// includes every Bitcoin app release known to the API, not just the latest one.
+fn list_catalog_apps(
+    device_info: &DeviceInfo,
+) -> Result<Vec<BitcoinAppInfo>, Box<dyn error::Error>> {
+    let resp_apps = minreq::Request::new(
+        minreq::Method::Get,
+        format!("{}/apps/by-target", BASE_API_V2_URL),
+    )
+    .with_param("livecommonversion", LIVE_COMMON_VERSION)
+    .with_param("provider", PROVIDER.to_string())
+    .with_param("target_id", device_info.target_id.to_string())
+    .with_param("firmware_version_name", device_info.version.clone())
+    .send()?;
+    Ok(resp_apps.json::<Vec<BitcoinAppInfo>>()?)
+}
+
 /// Get the Bitcoin apps information for this device from the "catalog" (as Ledger Live calls it).
 // This uses the v2 API. See for reference:
 // - https://github.com/LedgerHQ/ledger-live/blob/5a0a1aa5dc183116839851b79bceb6704f1de4b9/libs/ledger-live-common/src/apps/listApps/v2.ts
@@ -582,26 +646,14 @@ pub fn get_latest_apps(
     let mut bitcoin = None;
     let mut test = None;
 
-    let resp_apps = minreq::Request::new(
-        minreq::Method::Get,
-        format!("{}/apps/by-target", BASE_API_V2_URL),
-    )
-    .with_param("livecommonversion", LIVE_COMMON_VERSION)
-    .with_param("provider", PROVIDER.to_string())
-    .with_param("target_id", device_info.target_id.to_string())
-    .with_param("firmware_version_name", device_info.version.clone())
-    .send()?;
-    resp_apps
-        .json::<Vec<BitcoinAppInfo>>()?
-        .into_iter()
-        .for_each(|app| {
-            // FIXME: is versionName guaranteed to be the name? What's "version" for?
-            if app.version_name.to_lowercase() == "bitcoin" {
-                bitcoin = Some(app);
-            } else if app.version_name.to_lowercase() == "bitcoin test" {
-                test = Some(app);
-            }
-        });
+    list_catalog_apps(device_info)?.into_iter().for_each(|app| {
+        // FIXME: is versionName guaranteed to be the name? What's "version" for?
+        if app.version_name.to_lowercase() == "bitcoin" {
+            bitcoin = Some(app);
+        } else if app.version_name.to_lowercase() == "bitcoin test" {
+            test = Some(app);
+        }
+    });
 
     Ok((bitcoin, test))
 }
@@ -636,6 +688,50 @@ pub fn open_bitcoin_app(
     Ok(())
 }
 
+/// Get the name of the application currently running on the device. Returns `None` on older
+/// devices/firmwares that don't implement this instruction.
+pub fn current_open_app(
+    ledger_api: &TransportNativeHID,
+) -> Result<Option<String>, Box<dyn error::Error>> {
+    let answer = ledger_api.exchange(&GET_APP_AND_VERSION_COMMAND)?;
+    if answer.retcode() != StatusCode::OK as u16 {
+        return Ok(None);
+    }
+
+    let data = answer.data();
+    if data.first() != Some(&0x01) {
+        return Ok(None);
+    }
+    let name_len = *data.get(1).ok_or("Not enough data")? as usize;
+    let name = data.get(2..2 + name_len).ok_or("Not enough data")?;
+
+    Ok(Some(str::from_utf8(name)?.to_string()))
+}
+
+/// Ask the device to quit the currently running application and return to the dashboard.
+pub fn close_running_app(ledger_api: &TransportNativeHID) -> Result<(), Box<dyn error::Error>> {
+    let resp = ledger_api.exchange(&QUIT_APP_COMMAND)?;
+    if resp.retcode() != StatusCode::OK as u16 {
+        return Err(format!("Error closing the running app. Ledger response: {:#x?}.", resp).into());
+    }
+    Ok(())
+}
+
+/// Whether the Bitcoin (or Bitcoin Test) app is the one currently open on the device.
+pub fn is_bitcoin_app_open(
+    ledger_api: &TransportNativeHID,
+    is_testnet: bool,
+) -> Result<bool, Box<dyn error::Error>> {
+    let lowercase_app_name = if is_testnet {
+        "bitcoin test"
+    } else {
+        "bitcoin"
+    };
+    Ok(current_open_app(ledger_api)?
+        .map(|name| name.to_lowercase() == lowercase_app_name)
+        .unwrap_or(false))
+}
+
 /// Check whether the Ledger device is genuine.
 pub fn genuine_check(ledger_api: &TransportNativeHID) -> Result<(), Box<dyn error::Error>> {
     let device_info = DeviceInfo::new(ledger_api)?;
@@ -655,13 +751,41 @@ pub enum InstallErr {
     AlreadyInstalled,
     /// Couldn't get info about the Bitcoin app.
     AppNotFound,
+    /// No release matching the requested version was found in the catalog.
+    VersionNotFound,
+    /// The app installed on the device doesn't match the hash of the app that was requested.
+    HashMismatch { expected: String, got: String },
+    /// The Bitcoin app is open; the device must be on the dashboard to install/update an app.
+    WrongAppOpen,
     Any(Box<dyn error::Error>),
 }
 
+/// Re-read the app installed on the device and check its hash matches the one that was just
+/// requested for install, to guard against the HSM silently delivering the wrong binary.
+fn verify_installed_hash(
+    ledger_api: &TransportNativeHID,
+    is_testnet: bool,
+    expected: &str,
+) -> Result<(), InstallErr> {
+    let installed = bitcoin_app_installed(ledger_api, is_testnet)
+        .map_err(InstallErr::Any)?
+        .ok_or(InstallErr::AppNotFound)?;
+    let got = hex::encode(&installed.hash);
+    if got.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(InstallErr::HashMismatch {
+            expected: expected.to_string(),
+            got,
+        })
+    }
+}
+
 fn install_app(
     ledger_api: &TransportNativeHID,
     device_info: &DeviceInfo,
     app: &BitcoinAppInfo,
+    progress: &mut dyn FnMut(InstallProgress),
 ) -> Result<(), Box<dyn error::Error>> {
     // Make sure to properly escape the parameters in the request's parameter.
     let install_ws_url = UrlSerializer::new(format!("{}/install?", BASE_SOCKET_URL))
@@ -672,7 +796,7 @@ fn install_app(
         .append_pair("firmwareKey", &app.firmware_key)
         .append_pair("hash", &app.hash)
         .finish();
-    query_via_websocket(ledger_api, &install_ws_url)
+    query_via_websocket_with_progress(ledger_api, &install_ws_url, progress)
 }
 
 /// Install the Bitcoin application on this device. Set `is_testnet` to `true` to install the
@@ -680,11 +804,25 @@ fn install_app(
 pub fn install_bitcoin_app(
     ledger_api: &TransportNativeHID,
     is_testnet: bool,
+) -> Result<(), InstallErr> {
+    install_bitcoin_app_with_progress(ledger_api, is_testnet, &mut |_| {})
+}
+
+/// Same as `install_bitcoin_app`, but calls `progress` throughout the HSM install so a caller can
+/// render a progress bar instead of an indeterminate spinner.
+pub fn install_bitcoin_app_with_progress(
+    ledger_api: &TransportNativeHID,
+    is_testnet: bool,
+    progress: &mut dyn FnMut(InstallProgress),
 ) -> Result<(), InstallErr> {
     // First of all make sure it's not already installed.
     if is_bitcoin_app_installed(ledger_api, is_testnet).map_err(InstallErr::Any)? {
         return Err(InstallErr::AlreadyInstalled);
     }
+    // Installing requires the dashboard, not the Bitcoin app, to be the active context.
+    if is_bitcoin_app_open(ledger_api, is_testnet).map_err(InstallErr::Any)? {
+        return Err(InstallErr::WrongAppOpen);
+    }
 
     // Get the app info, necessary for the websocket query below.
     let device_info = DeviceInfo::new(ledger_api).map_err(InstallErr::Any)?;
@@ -693,7 +831,48 @@ pub fn install_bitcoin_app(
         .ok_or(InstallErr::AppNotFound)?;
 
     // Now install the app by connecting through their websocket thing to their HSM.
-    install_app(ledger_api, &device_info, &bitcoin_app).map_err(InstallErr::Any)?;
+    install_app(ledger_api, &device_info, &bitcoin_app, progress).map_err(InstallErr::Any)?;
+    verify_installed_hash(ledger_api, is_testnet, &bitcoin_app.hash)?;
+
+    Ok(())
+}
+
+/// Install a specific, pinned release of the Bitcoin application instead of always the latest.
+/// `version` is matched against each catalog entry's semver `version` field, e.g. `"2.2.1"`. This
+/// is useful to roll back or sidestep a latest release that's incompatible with a device's
+/// firmware.
+pub fn install_bitcoin_app_version(
+    ledger_api: &TransportNativeHID,
+    is_testnet: bool,
+    version: &str,
+) -> Result<(), InstallErr> {
+    // First of all make sure it's not already installed.
+    if is_bitcoin_app_installed(ledger_api, is_testnet).map_err(InstallErr::Any)? {
+        return Err(InstallErr::AlreadyInstalled);
+    }
+    // Installing requires the dashboard, not the Bitcoin app, to be the active context.
+    if is_bitcoin_app_open(ledger_api, is_testnet).map_err(InstallErr::Any)? {
+        return Err(InstallErr::WrongAppOpen);
+    }
+
+    let requested = semver::Version::parse(version).map_err(|e| InstallErr::Any(e.into()))?;
+    let lowercase_app_name = if is_testnet { "bitcoin test" } else { "bitcoin" };
+
+    let device_info = DeviceInfo::new(ledger_api).map_err(InstallErr::Any)?;
+    let bitcoin_app = list_catalog_apps(&device_info)
+        .map_err(InstallErr::Any)?
+        .into_iter()
+        .find(|app| {
+            app.version_name.to_lowercase() == lowercase_app_name
+                && semver::Version::parse(&app.version)
+                    .map(|v| v == requested)
+                    .unwrap_or(false)
+        })
+        .ok_or(InstallErr::VersionNotFound)?;
+
+    // Now install the app by connecting through their websocket thing to their HSM.
+    install_app(ledger_api, &device_info, &bitcoin_app, &mut |_| {}).map_err(InstallErr::Any)?;
+    verify_installed_hash(ledger_api, is_testnet, &bitcoin_app.hash)?;
 
     Ok(())
 }
@@ -707,19 +886,31 @@ pub enum UpdateErr {
     AppNotFound,
     /// The installed app is already the latest.
     AlreadyLatest,
+    /// The latest installable app still falls short of a required minimum feature version; the
+    /// device firmware itself needs to be upgraded first.
+    FirmwareUpgradeRequired {
+        installed: semver::Version,
+        required: semver::Version,
+    },
     Any(Box<dyn error::Error>),
 }
 
-/// Update the Bitcoin application on this device. Set `is_testnet` to `true` to install the
-/// testnet app instead.
-pub fn update_bitcoin_app(
+/// Update the Bitcoin application on this device, returning the metadata of the newly installed
+/// app.
+fn update_bitcoin_app_inner(
     ledger_api: &TransportNativeHID,
     is_testnet: bool,
-) -> Result<(), UpdateErr> {
+    progress: &mut dyn FnMut(InstallProgress),
+) -> Result<BitcoinAppInfo, UpdateErr> {
     // First of all make sure the app is installed. Get its details.
     let app = bitcoin_app_installed(ledger_api, is_testnet)
         .map_err(UpdateErr::Any)?
         .ok_or(UpdateErr::NotInstalled)?;
+    // The HSM install requires the dashboard to be the active context, so close the Bitcoin app
+    // if it's currently running. The user is prompted on-device to reopen it once done.
+    if is_bitcoin_app_open(ledger_api, is_testnet).map_err(UpdateErr::Any)? {
+        close_running_app(ledger_api).map_err(UpdateErr::Any)?;
+    }
     let installed_app = bitcoin_apps_by_hashes(vec![app.hash])
         .map_err(UpdateErr::Any)?
         .into_iter()
@@ -743,7 +934,78 @@ pub fn update_bitcoin_app(
     }
 
     // Now install the app by connecting through their websocket thing to their HSM.
-    install_app(ledger_api, &device_info, &latest_app).map_err(UpdateErr::Any)?;
+    install_app(ledger_api, &device_info, &latest_app, progress).map_err(UpdateErr::Any)?;
+
+    Ok(latest_app)
+}
+
+/// Update the Bitcoin application on this device. Set `is_testnet` to `true` to install the
+/// testnet app instead.
+pub fn update_bitcoin_app(ledger_api: &TransportNativeHID, is_testnet: bool) -> Result<(), UpdateErr> {
+    update_bitcoin_app_with_progress(ledger_api, is_testnet, &mut |_| {})
+}
+
+/// Same as `update_bitcoin_app`, but calls `progress` throughout the HSM update so a caller can
+/// render a progress bar instead of an indeterminate spinner.
+pub fn update_bitcoin_app_with_progress(
+    ledger_api: &TransportNativeHID,
+    is_testnet: bool,
+    progress: &mut dyn FnMut(InstallProgress),
+) -> Result<(), UpdateErr> {
+    update_bitcoin_app_inner(ledger_api, is_testnet, progress).map(|_| ())
+}
+
+/// Update the Bitcoin application, then check that the newly installed version meets a minimum
+/// feature requirement, e.g. `[2, 2, 0]` for Taproot support. Some device firmwares cap the latest
+/// installable app below the version that supports a given feature, in which case this returns
+/// `UpdateErr::FirmwareUpgradeRequired` instead of implying the update fixed everything.
+pub fn update_bitcoin_app_for_feature(
+    ledger_api: &TransportNativeHID,
+    is_testnet: bool,
+    min_feature_version: [u16; 3],
+) -> Result<(), UpdateErr> {
+    let required = semver::Version::new(
+        min_feature_version[0] as u64,
+        min_feature_version[1] as u64,
+        min_feature_version[2] as u64,
+    );
+
+    // If the app was already on the latest installable version, `update_bitcoin_app_inner` never
+    // installs anything and so never reports the version we ended up on. A firmware-capped
+    // device can already be sitting on that (sub-feature) latest version, so the feature check
+    // still needs to run against the currently installed app rather than being skipped.
+    let installed_version = match update_bitcoin_app_inner(ledger_api, is_testnet, &mut |_| {}) {
+        Ok(latest_app) => latest_app.version,
+        Err(UpdateErr::AlreadyLatest) => {
+            let app = bitcoin_app_installed(ledger_api, is_testnet)
+                .map_err(UpdateErr::Any)?
+                .ok_or(UpdateErr::NotInstalled)?;
+            let installed_app = bitcoin_apps_by_hashes(vec![app.hash])
+                .map_err(UpdateErr::Any)?
+                .into_iter()
+                .next()
+                .flatten()
+                .ok_or(UpdateErr::AppNotFound)?;
+            let installed = semver::Version::parse(&installed_app.version)
+                .map_err(|e| UpdateErr::Any(e.into()))?;
+            if installed < required {
+                return Err(UpdateErr::FirmwareUpgradeRequired {
+                    installed,
+                    required,
+                });
+            }
+            return Err(UpdateErr::AlreadyLatest);
+        }
+        Err(e) => return Err(e),
+    };
+
+    let installed = semver::Version::parse(&installed_version).map_err(|e| UpdateErr::Any(e.into()))?;
+    if installed < required {
+        return Err(UpdateErr::FirmwareUpgradeRequired {
+            installed,
+            required,
+        });
+    }
 
     Ok(())
 }
@@ -1,10 +1,20 @@
 use form_urlencoded::Serializer as UrlSerializer;
-use ledger_apdu::APDUCommand;
+use ledger_apdu::{APDUAnswer, APDUCommand};
 use serde_derive::Deserialize;
 
 use crate::baaca::ledger_service::{Model, Version};
 use ledger_transport_hidapi::{hidapi::HidApi, TransportNativeHID};
-use std::{error, str};
+use std::{
+    error,
+    ffi::CString,
+    fmt, str,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
 
 // https://github.com/LedgerHQ/ledger-live/blob/dd1d17fd3ce7ed42558204b2f93707fb9b1599de/libs/device-core/src/commands/use-cases/getVersion.ts#L6
 const GET_VERSION_COMMAND: APDUCommand<&[u8]> = APDUCommand {
@@ -42,6 +52,15 @@ const OPEN_APP_COMMAND_TEMPLATE: APDUCommand<&[u8]> = APDUCommand {
     data: &[],
 };
 
+// https://github.com/LedgerHQ/ledger-live/blob/5a0a1aa5dc183116839851b79bceb6704f1de4b9/libs/ledger-live-common/src/hw/getAppAndVersion.ts#L3
+const GET_APP_AND_VERSION_COMMAND: APDUCommand<&[u8]> = APDUCommand {
+    cla: 0xb0,
+    ins: 0x01,
+    p1: 0x00,
+    p2: 0x00,
+    data: &[],
+};
+
 #[allow(unused)]
 pub const LIVE_COMMON_VERSION: &str = "34.0.0";
 pub const PROVIDER: u32 = 1; // TODO: make it possible to set it.
@@ -50,47 +69,277 @@ pub const BASE_API_V1_URL: &str = "https://manager.api.live.ledger.com/api";
 pub const BASE_API_V2_URL: &str = "https://manager.api.live.ledger.com/api/v2";
 pub const BASE_SOCKET_URL: &str = "wss://scriptrunner.api.live.ledger.com/update";
 
-#[derive(Debug, Clone, Copy)]
+/// Abstracts over the way an APDU command reaches a Ledger device, so the parsing and flow logic
+/// in this module isn't tied to native USB HID and could be driven over WebUSB, an emulator, etc.
+pub trait ApduExchange {
+    fn exchange<D: AsRef<[u8]>>(
+        &self,
+        command: &APDUCommand<D>,
+    ) -> Result<APDUAnswer<Vec<u8>>, Box<dyn error::Error>>;
+}
+
+impl ApduExchange for TransportNativeHID {
+    fn exchange<D: AsRef<[u8]>>(
+        &self,
+        command: &APDUCommand<D>,
+    ) -> Result<APDUAnswer<Vec<u8>>, Box<dyn error::Error>> {
+        TransportNativeHID::exchange(self, command).map_err(Into::into)
+    }
+}
+
+/// Speaks the Speculos emulator's TCP APDU protocol, so the install/list logic in this module can
+/// be exercised without a physical device. Each request is a 4-byte big-endian length prefix
+/// followed by the raw APDU bytes. Each response is a 4-byte big-endian length prefix followed by
+/// the answer's data, then its 2-byte status word, which the length does not count.
+///
+/// See https://speculos.ledger.com/user/tcp.html
+pub struct SpeculosTransport {
+    stream: std::sync::Mutex<std::net::TcpStream>,
+}
+
+impl SpeculosTransport {
+    /// Connect to a running Speculos instance's APDU port (default `127.0.0.1:9999`).
+    pub fn connect(addr: impl std::net::ToSocketAddrs) -> Result<Self, Box<dyn error::Error>> {
+        Ok(Self {
+            stream: std::sync::Mutex::new(std::net::TcpStream::connect(addr)?),
+        })
+    }
+}
+
+impl ApduExchange for SpeculosTransport {
+    fn exchange<D: AsRef<[u8]>>(
+        &self,
+        command: &APDUCommand<D>,
+    ) -> Result<APDUAnswer<Vec<u8>>, Box<dyn error::Error>> {
+        use std::io::{Read, Write};
+
+        let data = command.data.as_ref();
+        let mut raw = Vec::with_capacity(5 + data.len());
+        raw.push(command.cla);
+        raw.push(command.ins);
+        raw.push(command.p1);
+        raw.push(command.p2);
+        raw.push(data.len() as u8);
+        raw.extend_from_slice(data);
+
+        let mut stream = self.stream.lock().unwrap();
+        stream.write_all(&(raw.len() as u32).to_be_bytes())?;
+        stream.write_all(&raw)?;
+
+        let mut len_buf = [0u8; 4];
+        stream.read_exact(&mut len_buf)?;
+        let mut answer = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+        stream.read_exact(&mut answer)?;
+
+        let mut status_buf = [0u8; 2];
+        stream.read_exact(&mut status_buf)?;
+        answer.extend_from_slice(&status_buf);
+
+        APDUAnswer::from_answer(answer)
+            .map_err(|e| format!("Malformed Speculos response: {:?}", e).into())
+    }
+}
+
+/// The return code ("status word") sent back by the device in response to an APDU command.
+// Taken from https://github.com/LedgerHQ/ledger-live/blob/4d1d7bb3462fd0c986ed587f0cf426afc96850c8/libs/ledgerjs/packages/errors/src/index.ts#L233
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u16)]
 pub enum StatusCode {
-    //ACCESS_CONDITION_NOT_FULFILLED = 0x9804,
-    //ALGORITHM_NOT_SUPPORTED = 0x9484,
-    //CLA_NOT_SUPPORTED = 0x6e00,
-    //CODE_BLOCKED = 0x9840,
-    //CODE_NOT_INITIALIZED = 0x9802,
-    //COMMAND_INCOMPATIBLE_FILE_STRUCTURE = 0x6981,
-    //CONDITIONS_OF_USE_NOT_SATISFIED = 0x6985,
-    //CONTRADICTION_INVALIDATION = 0x9810,
-    //CONTRADICTION_SECRET_CODE_STATUS = 0x9808,
-    //CUSTOM_IMAGE_BOOTLOADER = 0x662f,
-    //CUSTOM_IMAGE_EMPTY = 0x662e,
-    //FILE_ALREADY_EXISTS = 0x6a89,
-    //FILE_NOT_FOUND = 0x9404,
-    //GP_AUTH_FAILED = 0x6300,
-    //HALTED = 0x6faa,
-    //INCONSISTENT_FILE = 0x9408,
-    //INCORRECT_DATA = 0x6a80,
-    //INCORRECT_LENGTH = 0x6700,
-    //INCORRECT_P1_P2 = 0x6b00,
-    //INS_NOT_SUPPORTED = 0x6d00,
-    //DEVICE_NOT_ONBOARDED = 0x6d07,
-    //DEVICE_NOT_ONBOARDED_2 = 0x6611,
-    //INVALID_KCV = 0x9485,
-    //INVALID_OFFSET = 0x9402,
-    //LICENSING = 0x6f42,
-    //LOCKED_DEVICE = 0x5515,
-    //MAX_VALUE_REACHED = 0x9850,
-    //MEMORY_PROBLEM = 0x9240,
-    //MISSING_CRITICAL_PARAMETER = 0x6800,
-    //NO_EF_SELECTED = 0x9400,
-    //NOT_ENOUGH_MEMORY_SPACE = 0x6a84,
+    AccessConditionNotFulfilled = 0x9804,
+    AlgorithmNotSupported = 0x9484,
+    ClaNotSupported = 0x6e00,
+    CodeBlocked = 0x9840,
+    CodeNotInitialized = 0x9802,
+    CommandIncompatibleFileStructure = 0x6981,
+    ConditionsOfUseNotSatisfied = 0x6985,
+    ApplicationNotFound = 0x6a82,
+    ContradictionInvalidation = 0x9810,
+    ContradictionSecretCodeStatus = 0x9808,
+    CustomImageBootloader = 0x662f,
+    CustomImageEmpty = 0x662e,
+    FileAlreadyExists = 0x6a89,
+    FileNotFound = 0x9404,
+    GpAuthFailed = 0x6300,
+    Halted = 0x6faa,
+    InconsistentFile = 0x9408,
+    IncorrectData = 0x6a80,
+    IncorrectLength = 0x6700,
+    IncorrectP1P2 = 0x6b00,
+    InsNotSupported = 0x6d00,
+    DeviceNotOnboarded = 0x6d07,
+    DeviceNotOnboarded2 = 0x6611,
+    InvalidKcv = 0x9485,
+    InvalidOffset = 0x9402,
+    Licensing = 0x6f42,
+    LockedDevice = 0x5515,
+    MaxValueReached = 0x9850,
+    MemoryProblem = 0x9240,
+    MissingCriticalParameter = 0x6800,
+    NoEfSelected = 0x9400,
+    NotEnoughMemorySpace = 0x6a84,
     OK = 0x9000,
-    //PIN_REMAINING_ATTEMPTS = 0x63c0,
-    //REFERENCED_DATA_NOT_FOUND = 0x6a88,
-    //SECURITY_STATUS_NOT_SATISFIED = 0x6982,
-    //TECHNICAL_PROBLEM = 0x6f00,
-    //UNKNOWN_APDU = 0x6d02,
-    //USER_REFUSED_ON_DEVICE = 0x5501,
-    //NOT_ENOUGH_SPACE = 0x5102,
+    PinRemainingAttempts = 0x63c0,
+    ReferencedDataNotFound = 0x6a88,
+    SecurityStatusNotSatisfied = 0x6982,
+    TechnicalProblem = 0x6f00,
+    UnknownApdu = 0x6d02,
+    UserRefusedOnDevice = 0x5501,
+    NotEnoughSpace = 0x5102,
+}
+
+impl TryFrom<u16> for StatusCode {
+    type Error = ();
+
+    fn try_from(retcode: u16) -> Result<Self, Self::Error> {
+        Ok(match retcode {
+            0x9804 => Self::AccessConditionNotFulfilled,
+            0x9484 => Self::AlgorithmNotSupported,
+            0x6e00 => Self::ClaNotSupported,
+            0x9840 => Self::CodeBlocked,
+            0x9802 => Self::CodeNotInitialized,
+            0x6981 => Self::CommandIncompatibleFileStructure,
+            0x6985 => Self::ConditionsOfUseNotSatisfied,
+            0x6a82 => Self::ApplicationNotFound,
+            0x9810 => Self::ContradictionInvalidation,
+            0x9808 => Self::ContradictionSecretCodeStatus,
+            0x662f => Self::CustomImageBootloader,
+            0x662e => Self::CustomImageEmpty,
+            0x6a89 => Self::FileAlreadyExists,
+            0x9404 => Self::FileNotFound,
+            0x6300 => Self::GpAuthFailed,
+            0x6faa => Self::Halted,
+            0x9408 => Self::InconsistentFile,
+            0x6a80 => Self::IncorrectData,
+            0x6700 => Self::IncorrectLength,
+            0x6b00 => Self::IncorrectP1P2,
+            0x6d00 => Self::InsNotSupported,
+            0x6d07 => Self::DeviceNotOnboarded,
+            0x6611 => Self::DeviceNotOnboarded2,
+            0x9485 => Self::InvalidKcv,
+            0x9402 => Self::InvalidOffset,
+            0x6f42 => Self::Licensing,
+            0x5515 => Self::LockedDevice,
+            0x9850 => Self::MaxValueReached,
+            0x9240 => Self::MemoryProblem,
+            0x6800 => Self::MissingCriticalParameter,
+            0x9400 => Self::NoEfSelected,
+            0x6a84 => Self::NotEnoughMemorySpace,
+            0x9000 => Self::OK,
+            0x63c0 => Self::PinRemainingAttempts,
+            0x6a88 => Self::ReferencedDataNotFound,
+            0x6982 => Self::SecurityStatusNotSatisfied,
+            0x6f00 => Self::TechnicalProblem,
+            0x6d02 => Self::UnknownApdu,
+            0x5501 => Self::UserRefusedOnDevice,
+            0x5102 => Self::NotEnoughSpace,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// A non-OK status word returned by the device.
+#[derive(Debug, Clone, Copy)]
+pub struct StatusError(pub u16);
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match LedgerError::from(*self) {
+            LedgerError::Other(_) => match StatusCode::try_from(self.0) {
+                Ok(code) => write!(f, "device returned status {:?} ({:#06x})", code, self.0),
+                Err(()) => write!(f, "device returned an unrecognized status {:#06x}", self.0),
+            },
+            classified => write!(f, "{} ({:#06x})", classified, self.0),
+        }
+    }
+}
+
+impl error::Error for StatusError {}
+
+fn ensure_ok(retcode: u16) -> Result<(), StatusError> {
+    if retcode == StatusCode::OK as u16 {
+        Ok(())
+    } else {
+        Err(StatusError(retcode))
+    }
+}
+
+/// Whether a boxed error wraps a `StatusError` for a locked device.
+fn is_locked_error(e: &(dyn error::Error + 'static)) -> bool {
+    e.downcast_ref::<StatusError>()
+        .map(|se| se.0 == StatusCode::LockedDevice as u16)
+        .unwrap_or(false)
+}
+
+/// A classification of a non-OK APDU status word into a small set of actionable categories, so
+/// callers can decide programmatically whether to retry, prompt the user to confirm on-device, or
+/// give up, instead of pattern-matching on an English error string. Also backs `StatusError`'s
+/// human-readable message, so the status word -> meaning mapping lives in one place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    /// The device is locked; the user needs to enter their PIN.
+    Locked,
+    /// The user declined the action on the device.
+    UserRejected,
+    /// The instruction isn't supported by the application currently open, or no app is open.
+    NotSupported,
+    /// The requested application isn't installed on the device.
+    AppNotFound,
+    /// The PIN entered on the device was incorrect.
+    IncorrectPin,
+    /// The device hasn't been set up (onboarded) yet.
+    NotOnboarded,
+    /// Not enough free space on the device.
+    NotEnoughSpace,
+    /// Any other non-OK status word.
+    Other(StatusError),
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Locked => write!(f, "the device is locked. Unlock it and try again"),
+            Self::UserRejected => write!(f, "the action was refused on the device"),
+            Self::NotSupported => write!(
+                f,
+                "instruction not supported. Is the correct app open on the device?"
+            ),
+            Self::AppNotFound => write!(f, "the requested application isn't installed on the device"),
+            Self::IncorrectPin => write!(f, "incorrect PIN"),
+            Self::NotOnboarded => write!(f, "the device hasn't been set up yet"),
+            Self::NotEnoughSpace => write!(f, "not enough free space on the device"),
+            Self::Other(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for LedgerError {}
+
+impl From<StatusError> for LedgerError {
+    fn from(e: StatusError) -> Self {
+        match StatusCode::try_from(e.0) {
+            Ok(StatusCode::LockedDevice) => Self::Locked,
+            Ok(StatusCode::UserRefusedOnDevice)
+            | Ok(StatusCode::ConditionsOfUseNotSatisfied)
+            | Ok(StatusCode::SecurityStatusNotSatisfied) => Self::UserRejected,
+            Ok(StatusCode::InsNotSupported)
+            | Ok(StatusCode::ClaNotSupported)
+            | Ok(StatusCode::IncorrectLength) => Self::NotSupported,
+            Ok(StatusCode::ApplicationNotFound) => Self::AppNotFound,
+            Ok(StatusCode::PinRemainingAttempts) => Self::IncorrectPin,
+            Ok(StatusCode::DeviceNotOnboarded) | Ok(StatusCode::DeviceNotOnboarded2) => {
+                Self::NotOnboarded
+            }
+            Ok(StatusCode::NotEnoughMemorySpace) | Ok(StatusCode::NotEnoughSpace) => {
+                Self::NotEnoughSpace
+            }
+            _ => Self::Other(e),
+        }
+    }
+}
+
+/// Classify a boxed error into a `LedgerError`, if it wraps a `StatusError`.
+fn classify_status_error(e: &(dyn error::Error + 'static)) -> Option<LedgerError> {
+    e.downcast_ref::<StatusError>().copied().map(Into::into)
 }
 
 // NOTE: MCU target id is always == target_id in Ledger Live
@@ -110,8 +359,9 @@ impl DeviceInfo {
     /// Query information about this device.
     ///
     /// Adapted from https://github.com/LedgerHQ/ledger-live/blob/dd1d17fd3ce7ed42558204b2f93707fb9b1599de/libs/device-core/src/commands/use-cases/parseGetVersionResponse.ts
-    pub fn new(ledger_api: &TransportNativeHID) -> Result<Self, Box<dyn error::Error>> {
+    pub fn new<T: ApduExchange>(ledger_api: &T) -> Result<Self, Box<dyn error::Error>> {
         let ver_answer = ledger_api.exchange(&GET_VERSION_COMMAND)?;
+        ensure_ok(ver_answer.retcode())?;
         let data = ver_answer.data();
         let mut i = 0;
 
@@ -270,15 +520,99 @@ fn deser_apdu_command(hex_str: &str) -> Result<APDUCommand<Vec<u8>>, Box<dyn err
     })
 }
 
+/// The maximum size of a single APDU command's data field.
+const MAX_CHUNK_SIZE: usize = 255;
+
+/// "More data follows" flag set on P2 of every chunk but the last.
+const P2_MORE: u8 = 0x02;
+/// "This is a continuation of a previous command" flag set on P2 of every chunk but the first.
+const P2_EXTEND: u8 = 0x01;
+
+/// Send a payload larger than a single APDU's 255-byte data limit as a series of chunked APDUs
+/// sharing the same CLA/INS/P1, toggling the "more data follows"/"continuation" flags on P2.
+/// Aborts on the first non-OK status; only the final chunk's response data is returned.
+pub fn exchange_chunked<T: ApduExchange>(
+    ledger_api: &T,
+    cla: u8,
+    ins: u8,
+    p1: u8,
+    p2: u8,
+    data: &[u8],
+) -> Result<Vec<u8>, Box<dyn error::Error>> {
+    let chunks: Vec<&[u8]> = if data.is_empty() {
+        vec![&data[..]]
+    } else {
+        data.chunks(MAX_CHUNK_SIZE).collect()
+    };
+    let last = chunks.len() - 1;
+
+    let mut result = Vec::new();
+    for (i, chunk) in chunks.into_iter().enumerate() {
+        let mut chunk_p2 = p2;
+        if i > 0 {
+            chunk_p2 |= P2_EXTEND;
+        }
+        if i != last {
+            chunk_p2 |= P2_MORE;
+        }
+
+        let command = APDUCommand {
+            cla,
+            ins,
+            p1,
+            p2: chunk_p2,
+            data: chunk,
+        };
+        let answer = ledger_api.exchange(&command)?;
+        ensure_ok(answer.retcode())?;
+        if i == last {
+            result = answer.data().to_vec();
+        }
+    }
+
+    Ok(result)
+}
+
+/// Which leg of the HSM dance `InstallProgress` was reported during. The HSM usually sends a few
+/// standalone commands, then a bunch in bulk, then a success.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallPhase {
+    Exchange,
+    Bulk,
+    Success,
+}
+
+/// Progress of an install/update/genuine-check exchange with Ledger's remote HSM, reported after
+/// each APDU command processed over the websocket.
+#[derive(Debug, Clone, Copy)]
+pub struct InstallProgress {
+    pub phase: InstallPhase,
+    /// Number of APDU commands processed so far, across the whole websocket exchange.
+    pub current: usize,
+    /// Number of commands in the current "bulk" message, when known.
+    pub total: Option<usize>,
+}
+
 /// Some actions, such as installing apps or upgrading the firmware, are done in Ledger Live by
 /// opening a socket so a remote server communicates directly with the Ledger. It appears to be
 /// talking to an HSM up there which would manage sensitive actions.
 /// Parameters are passed directly in the url. Don't forget to escape the necessary characters!
-pub fn query_via_websocket(
-    ledger_api: &TransportNativeHID,
+pub fn query_via_websocket<T: ApduExchange>(
+    ledger_api: &T,
+    url: &str,
+) -> Result<(), Box<dyn error::Error>> {
+    query_via_websocket_with_progress(ledger_api, url, &mut |_| {})
+}
+
+/// Same as `query_via_websocket`, but calls `progress` after every APDU command exchanged with
+/// the device so a caller can render a progress bar instead of an indeterminate spinner.
+pub fn query_via_websocket_with_progress<T: ApduExchange>(
+    ledger_api: &T,
     url: &str,
+    progress: &mut dyn FnMut(InstallProgress),
 ) -> Result<(), Box<dyn error::Error>> {
     let (mut socket, _) = tungstenite::connect(url)?;
+    let mut current = 0;
 
     // https://github.com/LedgerHQ/ledger-live/blob/99879eb5bada1ecaea7a02d8886e16b44657af6d/libs/ledger-live-common/src/socket/index.ts#L95
     loop {
@@ -306,13 +640,19 @@ pub fn query_via_websocket(
                         "success"
                     } else {
                         eprintln!(
-                            "Error when installing app. Error code: {:#02x}. Resp: {:?}.",
-                            resp.retcode(),
+                            "Error when installing app: {}. Resp: {:?}.",
+                            StatusError(resp.retcode()),
                             resp
                         );
                         "error"
                     };
                     let resp_data = hex::encode(resp.data());
+                    current += 1;
+                    progress(InstallProgress {
+                        phase: InstallPhase::Exchange,
+                        current,
+                        total: None,
+                    });
 
                     let ws_resp = serde_json::json!({
                         "nonce": msg.nonce,
@@ -330,12 +670,20 @@ pub fn query_via_websocket(
                         Some(HsmMessageData::CommandList(l)) => l,
                         _ => return Err("Expecting a list of commands in bulk mode.".into()),
                     };
+                    let total = Some(commands.len());
                     for cmd_hex in commands {
                         if cmd_hex.is_empty() {
                             continue;
                         }
                         let command = deser_apdu_command(&cmd_hex)?;
-                        let _ = ledger_api.exchange(&command)?;
+                        let resp = ledger_api.exchange(&command)?;
+                        ensure_ok(resp.retcode())?;
+                        current += 1;
+                        progress(InstallProgress {
+                            phase: InstallPhase::Bulk,
+                            current,
+                            total,
+                        });
                     }
 
                     let ws_resp = serde_json::json!({
@@ -345,6 +693,11 @@ pub fn query_via_websocket(
                     });
                     socket.send(tungstenite::Message::Text(serde_json::to_string(&ws_resp)?))?;
                 } else if msg.query == "success" {
+                    progress(InstallProgress {
+                        phase: InstallPhase::Success,
+                        current,
+                        total: None,
+                    });
                     return Ok(());
                 } else if msg.query == "error" {
                     return Err(
@@ -372,10 +725,11 @@ pub fn query_via_websocket(
 }
 
 /// Get a list of applications installed on this device.
-pub fn list_installed_apps(
-    ledger_api: &TransportNativeHID,
+pub fn list_installed_apps<T: ApduExchange>(
+    ledger_api: &T,
 ) -> Result<Vec<InstalledApp>, Box<dyn error::Error>> {
     let mut answer = ledger_api.exchange(&LIST_APPS_COMMAND)?;
+    ensure_ok(answer.retcode())?;
     let mut data = answer.data();
 
     // See https://github.com/LedgerHQ/ledger-live/blob/99879eb5bada1ecaea7a02d8886e16b44657af6d/libs/ledger-live-common/src/hw/listApps.ts#L9
@@ -422,6 +776,7 @@ pub fn list_installed_apps(
         }
 
         answer = ledger_api.exchange(&CONTINUE_LIST_APPS_COMMAND)?;
+        ensure_ok(answer.retcode())?;
         data = answer.data();
     }
 
@@ -533,6 +888,38 @@ pub struct BitcoinAppV2 {
     pub hash: String,
 }
 
+impl BitcoinAppV2 {
+    /// Parse the firmware version this app release was built for, e.g. `2.1.0` out of
+    /// `"nanos/2.1.0/bitcoin_testnet/app_2.2.1"`.
+    pub fn required_firmware(&self) -> Option<semver::Version> {
+        semver::Version::parse(self.firmware.split('/').nth(1)?).ok()
+    }
+}
+
+/// The crate-wide minimum firmware version, used when no model-specific floor applies (see
+/// `LedgerModel::minimum_supported_firmware`).
+pub const DEPRECATE_VERSION_BEFORE: &str = "2.0.0";
+
+impl DeviceInfo {
+    /// Parse the device's Secure Element version as a semver `Version`, if possible.
+    pub fn semver(&self) -> Option<semver::Version> {
+        semver::Version::parse(self.se_version.as_deref()?).ok()
+    }
+}
+
+/// Whether this device's firmware is older than the minimum version supported by this crate, for
+/// `model`'s own floor (see `LedgerModel::minimum_supported_firmware`), or the crate-wide floor
+/// when the model is unknown. Unparseable or missing version information is not considered
+/// outdated.
+pub fn firmware_is_outdated(model: Option<LedgerModel>, info: &DeviceInfo) -> bool {
+    let minimum = match model {
+        Some(model) => model.minimum_supported_firmware(),
+        None => semver::Version::parse(DEPRECATE_VERSION_BEFORE)
+            .expect("DEPRECATE_VERSION_BEFORE is a valid semver string"),
+    };
+    info.semver().map(|v| v < minimum).unwrap_or(false)
+}
+
 /// Get the Bitcoin app information for this device. Set `is_testnet` to `true` to get the Test app
 /// instead.
 // This uses the v2 API. See for reference:
@@ -540,18 +927,11 @@ pub struct BitcoinAppV2 {
 // - https://github.com/LedgerHQ/ledger-live/blob/5a0a1aa5dc183116839851b79bceb6704f1de4b9/libs/device-core/src/managerApi/repositories/HttpManagerApiRepository.ts#L211
 // There is also another way which seems to be the API v1 way of getting the app info. See
 // above the commented out code.
-pub fn bitcoin_app(
+pub fn list_available_apps(
     device_info: &DeviceInfo,
-    is_testnet: bool,
-) -> Result<Option<BitcoinAppV2>, Box<dyn error::Error>> {
-    let lowercase_app_name = if is_testnet {
-        "bitcoin test"
-    } else {
-        "bitcoin"
-    };
+) -> Result<Vec<BitcoinAppV2>, Box<dyn error::Error>> {
     log::debug!("call ledger API");
     // TODO: minreq seems to be way too long to connect API
-    // TODO: Or can we map firmware_version_name values to the version names?
     let resp_apps = minreq::Request::new(
         minreq::Method::Get,
         format!("{}/apps/by-target", BASE_API_V2_URL),
@@ -562,36 +942,105 @@ pub fn bitcoin_app(
     .with_param("firmware_version_name", device_info.version.clone())
     .send()?;
     log::debug!("get response from ledger API");
-    resp_apps
-        .json::<Vec<BitcoinAppV2>>()
-        // FIXME: is versionName guaranteed to be the name? What's "version" for?
-        .map(|apps| {
-            apps.into_iter()
-                .find(|o| o.version_name.to_lowercase() == lowercase_app_name)
-        })
-        .map_err(|e| e.into())
+    resp_apps.json::<Vec<BitcoinAppV2>>().map_err(Into::into)
 }
 
-/// Open the given application on the device.
-pub fn open_bitcoin_app(
-    ledger_api: &TransportNativeHID,
+pub fn bitcoin_app(
+    device_info: &DeviceInfo,
     is_testnet: bool,
-) -> Result<(), Box<dyn error::Error>> {
-    let mut command = OPEN_APP_COMMAND_TEMPLATE;
-    command.data = if is_testnet {
-        b"Bitcoin Test"
+) -> Result<Option<BitcoinAppV2>, Box<dyn error::Error>> {
+    let lowercase_app_name = if is_testnet {
+        "bitcoin test"
     } else {
-        b"Bitcoin"
+        "bitcoin"
     };
+    // FIXME: is versionName guaranteed to be the name? What's "version" for?
+    Ok(list_available_apps(device_info)?
+        .into_iter()
+        .find(|o| o.version_name.to_lowercase() == lowercase_app_name))
+}
 
-    let resp = ledger_api.exchange(&command)?;
-    if resp.retcode() != StatusCode::OK as u16 {
-        return Err(format!("Error opening app. Ledger response: {:#x?}.", resp).into());
+/// Ask the device which application is currently open, if any, without opening a new one. This is
+/// a lightweight query: it works whether the dashboard or an app is the active context, unlike
+/// `DeviceInfo::new` which only reports dashboard-level version info.
+pub fn current_open_app<T: ApduExchange>(
+    ledger_api: &T,
+) -> Result<Option<String>, Box<dyn error::Error>> {
+    let answer = ledger_api.exchange(&GET_APP_AND_VERSION_COMMAND)?;
+    if answer.retcode() != StatusCode::OK as u16 {
+        return Ok(None);
     }
 
+    let data = answer.data();
+    if data.first() != Some(&0x01) {
+        return Ok(None);
+    }
+    let name_len = *data.get(1).ok_or("Not enough data")? as usize;
+    let name = data.get(2..2 + name_len).ok_or("Not enough data")?;
+
+    Ok(Some(str::from_utf8(name)?.to_string()))
+}
+
+/// Open the named application on the device.
+pub fn open_app<T: ApduExchange>(ledger_api: &T, name: &str) -> Result<(), Box<dyn error::Error>> {
+    // Routed through `exchange_chunked` (even though an app name always fits a single APDU) so
+    // this keeps working unmodified if Ledger ever needs a longer name than fits in one frame.
+    exchange_chunked(
+        ledger_api,
+        OPEN_APP_COMMAND_TEMPLATE.cla,
+        OPEN_APP_COMMAND_TEMPLATE.ins,
+        OPEN_APP_COMMAND_TEMPLATE.p1,
+        OPEN_APP_COMMAND_TEMPLATE.p2,
+        name.as_bytes(),
+    )?;
+
     Ok(())
 }
 
+/// Open the Bitcoin (or Bitcoin Test) application on the device.
+pub fn open_bitcoin_app<T: ApduExchange>(
+    ledger_api: &T,
+    is_testnet: bool,
+) -> Result<(), Box<dyn error::Error>> {
+    open_app(ledger_api, if is_testnet { "Bitcoin Test" } else { "Bitcoin" })
+}
+
+/// Install the given app on the device by connecting through Ledger's remote HSM, the same way
+/// Ledger Live does. Make sure to properly escape the parameters in the request's parameter.
+pub fn install_named_app<T: ApduExchange>(
+    ledger_api: &T,
+    device_info: &DeviceInfo,
+    app: &BitcoinAppV2,
+    progress: &mut dyn FnMut(InstallProgress),
+) -> Result<(), Box<dyn error::Error>> {
+    let install_ws_url = UrlSerializer::new(format!("{}/install?", BASE_SOCKET_URL))
+        .append_pair("targetId", &device_info.target_id.to_string())
+        .append_pair("perso", &app.perso)
+        .append_pair("deleteKey", &app.delete_key)
+        .append_pair("firmware", &app.firmware)
+        .append_pair("firmwareKey", &app.firmware_key)
+        .append_pair("hash", &app.hash)
+        .finish();
+    query_via_websocket_with_progress(ledger_api, &install_ws_url, progress)
+}
+
+/// Delete the given app from the device by connecting through Ledger's remote HSM. Mirrors the
+/// install URL shape, using the app's `deleteKey` rather than its `perso`.
+pub fn delete_app<T: ApduExchange>(
+    ledger_api: &T,
+    device_info: &DeviceInfo,
+    app: &BitcoinAppV2,
+) -> Result<(), Box<dyn error::Error>> {
+    let delete_ws_url = UrlSerializer::new(format!("{}/install?", BASE_SOCKET_URL))
+        .append_pair("targetId", &device_info.target_id.to_string())
+        .append_pair("deleteKey", &app.delete_key)
+        .append_pair("firmware", &app.firmware)
+        .append_pair("firmwareKey", &app.firmware_key)
+        .append_pair("hash", &app.hash)
+        .finish();
+    query_via_websocket(ledger_api, &delete_ws_url)
+}
+
 /// Call Ledger API in order to have app details
 pub fn get_app_version(info: &DeviceInfo, testnet: bool) -> Result<(Model, Version), String> {
     log::debug!("get_app_version()");
@@ -646,7 +1095,26 @@ pub fn get_app_version(info: &DeviceInfo, testnet: bool) -> Result<(Model, Versi
     }
 }
 
+/// Whether the device is ready to be queried/installed to, and if not, why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    /// The device is locked; the user needs to enter their PIN.
+    Locked,
+    /// The device is unlocked but sitting on the dashboard, no application is open.
+    AppNotOpen,
+    /// An application is open and the device can be queried/installed to.
+    Ready,
+    /// The device's firmware is older than `minimum`, the floor for its hardware model (see
+    /// `LedgerModel::minimum_supported_firmware`). Installing or updating apps is blocked until
+    /// the user updates their firmware.
+    Outdated {
+        current: semver::Version,
+        minimum: semver::Version,
+    },
+}
+
 pub struct VersionInfo {
+    pub status: ConnectionStatus,
     pub device_model: Option<Model>,
     pub device_version: Option<String>,
     pub mainnet_version: Option<Version>,
@@ -657,6 +1125,7 @@ pub struct VersionInfo {
 pub fn get_version_info<V, M>(
     transport: TransportNativeHID,
     actual_device_version: &Option<String>,
+    ledger_model: Option<LedgerModel>,
     version_callback: V,
     msg_callback: M,
 ) -> Result<VersionInfo, ()>
@@ -666,7 +1135,8 @@ where
 {
     log::info!("ledger::get_version_info()");
     let mut device_version: Option<String> = None;
-    let info = match device_info(&transport) {
+    let mut status = ConnectionStatus::AppNotOpen;
+    let info = match DeviceInfo::new(&transport) {
         Ok(info) => {
             log::info!("Device connected");
             log::debug!("Device version: {}", &info.version);
@@ -674,15 +1144,49 @@ where
                 &format!("Device connected, version: {}", &info.version),
                 false,
             );
+            let minimum = ledger_model
+                .map(LedgerModel::minimum_supported_firmware)
+                .unwrap_or_else(|| {
+                    semver::Version::parse(DEPRECATE_VERSION_BEFORE)
+                        .expect("DEPRECATE_VERSION_BEFORE is a valid semver string")
+                });
+            if let Some(current) = info.semver().filter(|v| *v < minimum) {
+                msg_callback(
+                    &format!(
+                        "Your Ledger's firmware ({}) is older than the minimum supported version \
+                         ({}). Please update it before installing or updating an app.",
+                        current, minimum
+                    ),
+                    true,
+                );
+                status = ConnectionStatus::Outdated { current, minimum };
+            }
             if actual_device_version.is_none() {
                 version_callback(Some("Ledger".to_string()), Some(info.version.clone()));
             }
             device_version = Some(info.version.clone());
+            if !matches!(status, ConnectionStatus::Outdated { .. }) {
+                status = match current_open_app(&transport) {
+                    // "BOLOS" is the dashboard's own name: no user-facing app is open.
+                    Ok(Some(name)) if name != "BOLOS" => ConnectionStatus::Ready,
+                    Ok(_) => ConnectionStatus::AppNotOpen,
+                    Err(e) if is_locked_error(e.as_ref()) => ConnectionStatus::Locked,
+                    Err(_) => ConnectionStatus::AppNotOpen,
+                };
+            }
             Some(info)
         }
         Err(e) => {
             log::debug!("Failed connect device: {}", &e);
-            msg_callback(&e, true);
+            status = if is_locked_error(e.as_ref()) {
+                ConnectionStatus::Locked
+            } else {
+                ConnectionStatus::AppNotOpen
+            };
+            msg_callback(
+                &format!("Error fetching device info: {}. Is the Ledger unlocked?", e),
+                true,
+            );
             None
         }
     };
@@ -732,6 +1236,7 @@ where
                 // clear message after app version check (after app install)
                 msg_callback("", false);
                 return Ok(VersionInfo {
+                    status,
                     device_model: Some(model),
                     device_version,
                     mainnet_version: Some(main_version),
@@ -743,11 +1248,22 @@ where
 
         }
         Ok(VersionInfo {
+            status,
             device_model: None,
             device_version,
             mainnet_version: None,
             testnet_version: None,
         })
+    } else if status == ConnectionStatus::Locked {
+        // Surface a locked device as actionable status rather than a bare connection error, so the
+        // UI can prompt the user to unlock it.
+        Ok(VersionInfo {
+            status,
+            device_model: None,
+            device_version: None,
+            mainnet_version: None,
+            testnet_version: None,
+        })
     } else {
         Err(())
     }
@@ -780,10 +1296,11 @@ where
         }
         Err(e) => {
             log::debug!("Error listing installed applications: {}.", e);
-            msg_callback(
-                &format!("Error listing installed applications: {}.", e),
-                true,
-            );
+            let message = match classify_status_error(e.as_ref()) {
+                Some(ledger_err) => format!("Error listing installed applications: {}.", ledger_err),
+                None => format!("Error listing installed applications: {}.", e),
+            };
+            msg_callback(&message, true);
             return Err(());
         }
     }
@@ -796,7 +1313,26 @@ where
     Ok((mainnet, testnet))
 }
 
-pub fn install_app<M>(transport: &TransportNativeHID, msg_callback: M, testnet: bool)
+/// An error arising from `install_app`, in addition to the human-readable reporting already done
+/// through `msg_callback`.
+#[derive(Debug)]
+pub enum InstallErr {
+    /// The device's firmware doesn't meet the firmware version required by the app being
+    /// installed. Returned before the websocket install is attempted, so callers can match on it
+    /// instead of parsing the reported message.
+    IncompatibleFirmware {
+        required: semver::Version,
+        found: semver::Version,
+    },
+    /// Any other failure; already reported to the user via `msg_callback`.
+    Other,
+}
+
+pub fn install_app<M>(
+    transport: &TransportNativeHID,
+    msg_callback: M,
+    testnet: bool,
+) -> Result<(), InstallErr>
 where
     M: Fn(&str, bool),
 {
@@ -804,49 +1340,200 @@ where
 
     msg_callback("Get device info from API...", false);
     if let Ok(device_info) = device_info(transport) {
+        if firmware_is_outdated(None, &device_info) {
+            msg_callback(
+                &format!(
+                    "Your Ledger's firmware ({}) is older than the minimum supported version \
+                     ({}). Please update it before installing an app.",
+                    device_info.version, DEPRECATE_VERSION_BEFORE
+                ),
+                true,
+            );
+            return Err(InstallErr::Other);
+        }
         let bitcoin_app = match bitcoin_app(&device_info, testnet) {
             Ok(Some(a)) => a,
             Ok(None) => {
                 msg_callback("Could not get info about Bitcoin app.", true);
-                return;
+                return Err(InstallErr::Other);
             }
             Err(e) => {
                 msg_callback(
                     &format!("Error querying info about Bitcoin app: {}.", e),
                     true,
                 );
-                return;
+                return Err(InstallErr::Other);
             }
         };
+        if let (Some(required), Some(found)) =
+            (bitcoin_app.required_firmware(), device_info.semver())
+        {
+            if required > found {
+                msg_callback(
+                    &format!(
+                        "This app requires firmware {} but the device is running {}. Please \
+                         update your Ledger's firmware first.",
+                        required, found
+                    ),
+                    true,
+                );
+                return Err(InstallErr::IncompatibleFirmware { required, found });
+            }
+        }
         msg_callback(
             "Installing, please allow Ledger manager on device...",
             false,
         );
-        // Now install the app by connecting through their websocket thing to their HSM. Make sure to
-        // properly escape the parameters in the request's parameter.
-        let install_ws_url = UrlSerializer::new(format!("{}/install?", BASE_SOCKET_URL))
-            .append_pair("targetId", &device_info.target_id.to_string())
-            .append_pair("perso", &bitcoin_app.perso)
-            .append_pair("deleteKey", &bitcoin_app.delete_key)
-            .append_pair("firmware", &bitcoin_app.firmware)
-            .append_pair("firmwareKey", &bitcoin_app.firmware_key)
-            .append_pair("hash", &bitcoin_app.hash)
-            .finish();
         msg_callback("Install app...", false);
-        if let Err(e) = query_via_websocket(transport, &install_ws_url) {
-            msg_callback(
-                &format!(
+        // `p.current` counts every command of the exchange while `p.total` only counts the bulk
+        // message's, so progress within a bulk is measured from where that bulk started.
+        let mut bulk_start: Option<usize> = None;
+        if let Err(e) = install_named_app(transport, &device_info, &bitcoin_app, &mut |p| {
+            let label = match p.phase {
+                InstallPhase::Exchange => {
+                    bulk_start = None;
+                    "Installing".to_string()
+                }
+                InstallPhase::Bulk => match p.total {
+                    Some(total) => {
+                        let start = *bulk_start.get_or_insert(p.current - 1);
+                        format!("Installing ({}/{})", p.current - start, total)
+                    }
+                    None => "Installing".to_string(),
+                },
+                InstallPhase::Success => "Installed".to_string(),
+            };
+            msg_callback(&label, false);
+        }) {
+            let message = match classify_status_error(e.as_ref()) {
+                Some(ledger_err) => format!("Could not install the Bitcoin app: {}.", ledger_err),
+                None => format!(
                     "Got an error when installing Bitcoin app from Ledger's remote HSM: {}.",
                     e
                 ),
-                false,
-            );
-            return;
+            };
+            msg_callback(&message, false);
+            return Err(InstallErr::Other);
         }
         msg_callback("Successfully installed the app.", false);
+        Ok(())
     } else {
         msg_callback("Fail to fetch device info!", true);
+        Err(InstallErr::Other)
+    }
+}
+
+/// Uninstall the Bitcoin (or Bitcoin Test) app from the device via Ledger's remote HSM. Follows
+/// the same `msg_callback` reporting convention as `install_app`.
+pub fn uninstall_app<M>(transport: &TransportNativeHID, msg_callback: M, testnet: bool)
+where
+    M: Fn(&str, bool),
+{
+    log::debug!("ledger::uninstall_app(testnet={})", testnet);
+
+    msg_callback("Get device info from API...", false);
+    let device_info = match device_info(transport) {
+        Ok(info) => info,
+        Err(e) => {
+            msg_callback(&e, true);
+            return;
+        }
+    };
+    let bitcoin_app = match bitcoin_app(&device_info, testnet) {
+        Ok(Some(a)) => a,
+        Ok(None) => {
+            msg_callback("Could not get info about Bitcoin app.", true);
+            return;
+        }
+        Err(e) => {
+            msg_callback(
+                &format!("Error querying info about Bitcoin app: {}.", e),
+                true,
+            );
+            return;
+        }
+    };
+
+    msg_callback(
+        "Uninstalling, please allow Ledger manager on device...",
+        false,
+    );
+    if let Err(e) = delete_app(transport, &device_info, &bitcoin_app) {
+        let message = match classify_status_error(e.as_ref()) {
+            Some(ledger_err) => format!("Could not uninstall the Bitcoin app: {}.", ledger_err),
+            None => format!(
+                "Got an error when uninstalling Bitcoin app from Ledger's remote HSM: {}.",
+                e
+            ),
+        };
+        msg_callback(&message, true);
+        return;
+    }
+    msg_callback("Successfully uninstalled the app.", false);
+}
+
+/// Upgrade the installed Bitcoin (or Bitcoin Test) app to the latest catalog version, by
+/// uninstalling then reinstalling it. No-op (with a message) if the installed version already
+/// matches the latest one available, or if the app isn't installed at all.
+pub fn upgrade_app<M>(transport: &TransportNativeHID, msg_callback: M, testnet: bool)
+where
+    M: Fn(&str, bool),
+{
+    log::debug!("ledger::upgrade_app(testnet={})", testnet);
+
+    msg_callback("Querying installed apps. Please confirm on device.", false);
+    let (mainnet_installed, testnet_installed) = match check_apps_installed(transport, &msg_callback)
+    {
+        Ok(r) => r,
+        Err(()) => return,
+    };
+    if (testnet && !testnet_installed) || (!testnet && !mainnet_installed) {
+        msg_callback("The app isn't installed, nothing to upgrade.", true);
+        return;
+    }
+
+    let device_info = match device_info(transport) {
+        Ok(info) => info,
+        Err(e) => {
+            msg_callback(&e, true);
+            return;
+        }
+    };
+    let (_, installed_version) = match get_app_version(&device_info, testnet) {
+        Ok(r) => r,
+        Err(e) => {
+            msg_callback(&e, true);
+            return;
+        }
+    };
+    let latest_app = match bitcoin_app(&device_info, testnet) {
+        Ok(Some(a)) => a,
+        Ok(None) => {
+            msg_callback("Could not get info about the latest Bitcoin app.", true);
+            return;
+        }
+        Err(e) => {
+            msg_callback(
+                &format!("Error querying info about the latest Bitcoin app: {}.", e),
+                true,
+            );
+            return;
+        }
+    };
+    let latest_version = latest_app
+        .firmware
+        .rsplit('/')
+        .next()
+        .map(|v| v.replace("app_", ""));
+    if matches!(installed_version, Version::Installed(ref v) if Some(v) == latest_version.as_ref())
+    {
+        msg_callback("The installed app is already the latest version.", false);
+        return;
     }
+
+    msg_callback("Uninstalling the outdated app...", false);
+    uninstall_app(transport, &msg_callback, testnet);
+    let _ = install_app(transport, msg_callback, testnet);
 }
 
 pub fn ledger_api() -> Result<HidApi, String> {
@@ -858,3 +1545,295 @@ pub fn device_info(ledger_api: &TransportNativeHID) -> Result<DeviceInfo, String
     DeviceInfo::new(ledger_api)
         .map_err(|e| format!("Error fetching device info: {}. Is the Ledger unlocked?", e))
 }
+
+/// Ledger's USB vendor id.
+const LEDGER_VID: u16 = 0x2c97;
+
+/// The hardware model of a connected Ledger device, identified from its USB product id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerModel {
+    NanoS,
+    NanoX,
+    NanoSPlus,
+    Stax,
+    Flex,
+    Unknown(u16),
+}
+
+impl LedgerModel {
+    /// Identify the model from the product id reported over USB.
+    ///
+    /// Ranges taken from https://github.com/LedgerHQ/ledgerjs/blob/master/packages/devices/src/index.ts
+    fn from_product_id(pid: u16) -> Self {
+        match pid {
+            0x0001 | 0x1000..=0x101f => Self::NanoS,
+            0x0004 | 0x4000..=0x401f => Self::NanoX,
+            0x0005 | 0x5000..=0x501f => Self::NanoSPlus,
+            0x0006 | 0x6000..=0x601f => Self::Stax,
+            0x0007 | 0x7000..=0x701f => Self::Flex,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// The minimum firmware version this crate supports for this hardware model. Older,
+    /// discontinued models can be stuck on a firmware line that never reaches
+    /// `DEPRECATE_VERSION_BEFORE`; this is where that floor gets lowered (or raised) per model as
+    /// we learn about them. Unknown models fall back to the crate-wide floor.
+    pub fn minimum_supported_firmware(&self) -> semver::Version {
+        let version = match self {
+            Self::NanoS => "1.6.0",
+            Self::NanoX | Self::NanoSPlus | Self::Stax | Self::Flex | Self::Unknown(_) => {
+                DEPRECATE_VERSION_BEFORE
+            }
+        };
+        semver::Version::parse(version)
+            .expect("minimum_supported_firmware entries are valid semver strings")
+    }
+}
+
+/// A Ledger device found on the USB bus, not connected to yet.
+#[derive(Debug, Clone)]
+pub struct DiscoveredDevice {
+    pub model: LedgerModel,
+    /// The device's USB serial number, when the OS reports one. Used to tell apart several
+    /// connected Ledgers, since `path` is only stable for the lifetime of one enumeration.
+    pub serial: Option<String>,
+    path: CString,
+}
+
+impl DiscoveredDevice {
+    /// Open a transport to communicate with this device.
+    pub fn open(&self, api: &HidApi) -> Result<TransportNativeHID, String> {
+        let device = api
+            .device_list()
+            .find(|d| d.path() == self.path.as_c_str())
+            .ok_or_else(|| "Device was disconnected since it was discovered.".to_string())?;
+        TransportNativeHID::open_device(api, device)
+            .map_err(|e| format!("Error opening device: {}.", e))
+    }
+
+    /// A short human-readable label identifying this device, suitable for a "choose a device"
+    /// prompt when more than one Ledger is connected.
+    pub fn describe(&self) -> String {
+        match &self.serial {
+            Some(serial) => format!("{:?} ({})", self.model, serial),
+            None => format!("{:?}", self.model),
+        }
+    }
+}
+
+/// List all Ledger devices currently connected over USB, without opening them. The caller can
+/// inspect the `model` of each to let the user pick one, then call `DiscoveredDevice::open`. When
+/// more than one device is returned, use each entry's `serial` (surfaced via `describe`) to let
+/// the user disambiguate.
+pub fn list_devices(api: &HidApi) -> Vec<DiscoveredDevice> {
+    api.device_list()
+        .filter(|d| d.vendor_id() == LEDGER_VID)
+        .map(|d| DiscoveredDevice {
+            model: LedgerModel::from_product_id(d.product_id()),
+            serial: d.serial_number().map(str::to_string),
+            path: d.path().to_owned(),
+        })
+        .collect()
+}
+
+/// Find a previously discovered device by its USB serial number, to reopen or distinguish a
+/// specific Ledger among several connected at once.
+pub fn find_device_by_serial<'a>(
+    devices: &'a [DiscoveredDevice],
+    serial: &str,
+) -> Option<&'a DiscoveredDevice> {
+    devices
+        .iter()
+        .find(|d| d.serial.as_deref() == Some(serial))
+}
+
+/// A structured snapshot of the first connected Ledger device's status, combining USB-level model
+/// detection with on-device queries, instead of the bag of loosely related `Option`s returned by
+/// `device_info`/`get_version_info`.
+#[derive(Debug, Clone)]
+pub struct DeviceStatus {
+    pub connected: bool,
+    pub locked: bool,
+    pub model: Option<LedgerModel>,
+    pub firmware_version: Option<String>,
+    pub installed_apps: Vec<InstalledApp>,
+}
+
+impl DeviceStatus {
+    fn disconnected() -> Self {
+        Self {
+            connected: false,
+            locked: false,
+            model: None,
+            firmware_version: None,
+            installed_apps: Vec::new(),
+        }
+    }
+}
+
+/// Query a structured snapshot of the first connected Ledger device's status: whether it's
+/// connected, locked, its hardware model, firmware version, and the apps installed on it.
+pub fn device_status(api: &HidApi) -> DeviceStatus {
+    let Some(device) = list_devices(api).into_iter().next() else {
+        return DeviceStatus::disconnected();
+    };
+    let model = Some(device.model);
+
+    let transport = match device.open(api) {
+        Ok(t) => t,
+        Err(_) => {
+            return DeviceStatus {
+                model,
+                ..DeviceStatus::disconnected()
+            }
+        }
+    };
+
+    match DeviceInfo::new(&transport) {
+        Ok(info) => DeviceStatus {
+            connected: true,
+            locked: false,
+            model,
+            firmware_version: Some(info.version),
+            installed_apps: list_installed_apps(&transport).unwrap_or_default(),
+        },
+        Err(e) => DeviceStatus {
+            connected: true,
+            locked: is_locked_error(e.as_ref()),
+            model,
+            firmware_version: None,
+            installed_apps: Vec::new(),
+        },
+    }
+}
+
+/// A state transition detected by `Watcher` while polling for a Ledger device.
+#[derive(Debug, Clone)]
+pub enum DeviceEvent {
+    Connected(DeviceInfo),
+    Disconnected,
+    Locked,
+    /// The named application (other than the dashboard) became the active context.
+    AppOpened(String),
+    /// The previously open application was closed, back to the dashboard.
+    AppClosed,
+}
+
+/// Number of consecutive enumeration misses required before `Watcher` emits `Disconnected`. This
+/// debounces the brief USB re-enumeration that happens during an app install, which would
+/// otherwise be reported as a spurious unplug.
+const DISCONNECT_DEBOUNCE_MISSES: u32 = 3;
+
+/// Polls on a background thread for a connected Ledger device to appear, disappear, change lock
+/// state, or open/close an application, and reports transitions through a channel. Dropping the
+/// `Watcher` stops the thread and joins it.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl Watcher {
+    /// Start polling for device state changes, checking at most every `poll_interval`.
+    pub fn start(poll_interval: Duration) -> (Self, mpsc::Receiver<DeviceEvent>) {
+        let (tx, rx) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_thread = stop.clone();
+
+        let handle = thread::spawn(move || {
+            let mut was_connected = false;
+            let mut was_locked = false;
+            let mut consecutive_misses = 0u32;
+            // "BOLOS" is the dashboard's own name, not a user-facing app.
+            let mut open_app: Option<String> = None;
+
+            while !stop_thread.load(Ordering::Relaxed) {
+                let events: Vec<DeviceEvent> = match ledger_api().and_then(|api| {
+                    TransportNativeHID::new(&api).map_err(|e| e.to_string())
+                }) {
+                    Ok(transport) => {
+                        consecutive_misses = 0;
+                        match DeviceInfo::new(&transport) {
+                            Ok(info) => {
+                                // Emit `Connected` on the disconnected->connected transition, and
+                                // also on locked->unlocked: a device connected-while-locked never
+                                // got a `Connected` carrying its `DeviceInfo`, so unlocking it is
+                                // the first chance to deliver one.
+                                let mut events = Vec::new();
+                                if !was_connected || was_locked {
+                                    events.push(DeviceEvent::Connected(info));
+                                }
+                                was_connected = true;
+                                was_locked = false;
+                                let now_open = match current_open_app(&transport) {
+                                    Ok(Some(name)) if name != "BOLOS" => Some(name),
+                                    _ => None,
+                                };
+                                if now_open != open_app {
+                                    events.push(match &now_open {
+                                        Some(name) => DeviceEvent::AppOpened(name.clone()),
+                                        None => DeviceEvent::AppClosed,
+                                    });
+                                    open_app = now_open;
+                                }
+                                events
+                            }
+                            Err(e) if is_locked_error(e.as_ref()) => {
+                                let events = if was_locked {
+                                    Vec::new()
+                                } else {
+                                    vec![DeviceEvent::Locked]
+                                };
+                                was_connected = true;
+                                was_locked = true;
+                                events
+                            }
+                            Err(_) => Vec::new(),
+                        }
+                    }
+                    Err(_) => {
+                        open_app = None;
+                        if was_connected {
+                            consecutive_misses += 1;
+                            if consecutive_misses >= DISCONNECT_DEBOUNCE_MISSES {
+                                was_connected = false;
+                                was_locked = false;
+                                vec![DeviceEvent::Disconnected]
+                            } else {
+                                Vec::new()
+                            }
+                        } else {
+                            Vec::new()
+                        }
+                    }
+                };
+
+                for event in events {
+                    if tx.send(event).is_err() {
+                        // The receiver was dropped, no point in continuing to poll.
+                        return;
+                    }
+                }
+
+                thread::sleep(poll_interval);
+            }
+        });
+
+        (
+            Self {
+                stop,
+                handle: Some(handle),
+            },
+            rx,
+        )
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}